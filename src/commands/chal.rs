@@ -0,0 +1,38 @@
+use log::error;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+use crate::StarRealmsSharedContainer;
+
+#[command]
+#[description = "List the current open Star Realms challenges"]
+async fn chal(ctx: &Context, msg: &Message) -> CommandResult {
+    let sr_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<StarRealmsSharedContainer>()
+            .expect("Expected StarRealmsSharedContainer in TypeMap.")
+            .clone()
+    };
+    let sr = sr_lock.write().await;
+    let activity = sr.sr.activity().await.expect("Could not get activity");
+    for chal in activity.challenges {
+        if let Err(why) = msg
+            .channel_id
+            .say(
+                &ctx.http,
+                format!(
+                    "Challenge from: {} to {}",
+                    chal.challengername, chal.opponentname
+                ),
+            )
+            .await
+        {
+            error!("Error sending message: {:?}", why);
+        }
+    }
+
+    Ok(())
+}
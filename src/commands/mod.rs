@@ -0,0 +1,38 @@
+use std::collections::HashSet;
+
+use serenity::framework::standard::macros::{group, help};
+use serenity::framework::standard::{
+    help_commands, Args, CommandGroup, CommandResult, HelpOptions,
+};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+
+mod chal;
+mod setchannel;
+mod subscribe;
+mod unsubscribe;
+mod version;
+
+use chal::CHAL_COMMAND;
+use setchannel::SETCHANNEL_COMMAND;
+use subscribe::SUBSCRIBE_COMMAND;
+use unsubscribe::UNSUBSCRIBE_COMMAND;
+use version::VERSION_COMMAND;
+
+#[group]
+#[commands(chal, version, setchannel, subscribe, unsubscribe)]
+pub struct General;
+
+#[help]
+pub async fn help(
+    ctx: &Context,
+    msg: &Message,
+    args: Args,
+    help_options: &'static HelpOptions,
+    groups: &[&'static CommandGroup],
+    owners: HashSet<UserId>,
+) -> CommandResult {
+    let _ = help_commands::with_embeds(ctx, msg, args, help_options, groups, owners).await;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use log::error;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+use crate::guild_options::GuildOptionsContainer;
+
+#[command]
+#[description = "Register this channel to receive Star Realms notifications"]
+#[only_in(guilds)]
+#[required_permissions(MANAGE_GUILD)]
+async fn setchannel(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.expect("Expected to be run in a guild");
+
+    let guild_options_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<GuildOptionsContainer>()
+            .expect("Expected GuildOptionsContainer in TypeMap.")
+            .clone()
+    };
+
+    let mut guild_options = guild_options_lock.write().await;
+    if let Err(why) = guild_options.set_channel(guild_id, msg.channel_id).await {
+        error!("Error setting notification channel: {:?}", why);
+        return Ok(());
+    }
+
+    if let Err(why) = msg
+        .channel_id
+        .say(
+            &ctx.http,
+            "This channel will now receive Star Realms notifications.",
+        )
+        .await
+    {
+        error!("Error sending message: {:?}", why);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use log::error;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+use crate::subscriptions::SubscriptionsContainer;
+
+#[command]
+#[description = "Subscribe to DMs about a Star Realms player's turns, challenges, and finished games"]
+#[usage = "<player>"]
+async fn subscribe(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let player = args.rest().trim().to_string();
+    if player.is_empty() {
+        if let Err(why) = msg
+            .channel_id
+            .say(&ctx.http, "Usage: !subscribe <player>")
+            .await
+        {
+            error!("Error sending message: {:?}", why);
+        }
+        return Ok(());
+    }
+
+    let subscriptions_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<SubscriptionsContainer>()
+            .expect("Expected SubscriptionsContainer in TypeMap.")
+            .clone()
+    };
+
+    let mut subscriptions = subscriptions_lock.write().await;
+    if let Err(why) = subscriptions.subscribe(msg.author.id, player.clone()).await {
+        error!("Error subscribing {}: {:?}", msg.author.id, why);
+        return Ok(());
+    }
+
+    if let Err(why) = msg
+        .channel_id
+        .say(
+            &ctx.http,
+            format!("You'll now be DMed about {}'s turns and challenges.", player),
+        )
+        .await
+    {
+        error!("Error sending message: {:?}", why);
+    }
+
+    Ok(())
+}
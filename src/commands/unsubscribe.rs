@@ -0,0 +1,35 @@
+use log::error;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+use crate::subscriptions::SubscriptionsContainer;
+
+#[command]
+#[description = "Stop receiving Star Realms DM notifications"]
+async fn unsubscribe(ctx: &Context, msg: &Message) -> CommandResult {
+    let subscriptions_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<SubscriptionsContainer>()
+            .expect("Expected SubscriptionsContainer in TypeMap.")
+            .clone()
+    };
+
+    let mut subscriptions = subscriptions_lock.write().await;
+    if let Err(why) = subscriptions.unsubscribe(msg.author.id).await {
+        error!("Error unsubscribing {}: {:?}", msg.author.id, why);
+        return Ok(());
+    }
+
+    if let Err(why) = msg
+        .channel_id
+        .say(&ctx.http, "You will no longer receive Star Realms DMs.")
+        .await
+    {
+        error!("Error sending message: {:?}", why);
+    }
+
+    Ok(())
+}
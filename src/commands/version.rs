@@ -0,0 +1,19 @@
+use log::error;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+#[command]
+#[description = "Show the running Starbot version"]
+async fn version(ctx: &Context, msg: &Message) -> CommandResult {
+    if let Err(why) = msg
+        .channel_id
+        .say(&ctx.http, format!("Starbot {}", env!("CARGO_PKG_VERSION")))
+        .await
+    {
+        error!("Error sending message: {:?}", why);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::error;
+use star_realms_rs::Game;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::storage::Storage;
+
+/// Delay recommended between polls while a game keeps flipping turns.
+pub(crate) const MIN_POLL_DELAY: Duration = Duration::from_secs(5);
+/// Delay recommended once a game has gone quiet for a while.
+pub(crate) const MAX_POLL_DELAY: Duration = Duration::from_secs(60);
+/// Quiet ticks (no new turn) before an actor backs off to `MAX_POLL_DELAY`,
+/// chosen to match the 30 minutes the shared switch used to wait at the
+/// `MIN_POLL_DELAY` cadence.
+const QUIET_TICKS_BEFORE_BACKOFF: u32 = 360;
+
+/// The result of feeding an actor a fresh snapshot of its game.
+pub struct GameUpdate {
+    /// `Some(game)` if this is a new turn since the actor last saw one.
+    pub new_turn: Option<Game>,
+    /// How long this game's actor recommends waiting before polling again.
+    pub next_poll_delay: Duration,
+}
+
+/// Command sent to a per-game actor.
+enum GameCommand {
+    /// Latest snapshot of this game from the Star Realms API.
+    Update(Game, oneshot::Sender<GameUpdate>),
+    /// Stop the actor's task.
+    Shutdown,
+}
+
+/// A handle to a running per-game actor task. Cheap to clone.
+#[derive(Clone)]
+pub struct GameActorHandle {
+    tx: mpsc::Sender<GameCommand>,
+}
+
+impl GameActorHandle {
+    /// Feed the actor the latest snapshot of its game, returning whether
+    /// it's a new turn and how long to wait before polling this game again.
+    pub async fn update(&self, game: Game) -> GameUpdate {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self
+            .tx
+            .send(GameCommand::Update(game, reply_tx))
+            .await
+            .is_err()
+        {
+            return GameUpdate {
+                new_turn: None,
+                next_poll_delay: MIN_POLL_DELAY,
+            };
+        }
+        reply_rx.await.unwrap_or(GameUpdate {
+            new_turn: None,
+            next_poll_delay: MIN_POLL_DELAY,
+        })
+    }
+
+    /// Ask the actor's task to stop.
+    pub async fn shutdown(&self) {
+        let _ = self.tx.send(GameCommand::Shutdown).await;
+    }
+}
+
+/// Spawn a per-game actor owning `game_id`'s turn state, seeded with
+/// `last_turn` as loaded from storage at startup. Each actor tracks its own
+/// streak of quiet ticks (no new turn) and recommends its own poll delay,
+/// rather than the bot as a whole backing off on a single shared timer.
+pub fn spawn(game_id: i64, last_turn: Option<String>, storage: Arc<Storage>) -> GameActorHandle {
+    let (tx, mut rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        let mut last_turn = last_turn;
+        let mut quiet_ticks: u32 = 0;
+
+        while let Some(command) = rx.recv().await {
+            match command {
+                GameCommand::Update(game, reply) => {
+                    let which_turn = game.which_turn();
+                    let is_new_turn = last_turn.as_deref() != Some(which_turn.as_str());
+
+                    if is_new_turn {
+                        if let Err(why) = storage.set_game_turn(game_id, &which_turn).await {
+                            error!("Error persisting turn for game {}: {:?}", game_id, why);
+                        }
+                        last_turn = Some(which_turn);
+                        quiet_ticks = 0;
+                        let _ = reply.send(GameUpdate {
+                            new_turn: Some(game),
+                            next_poll_delay: MIN_POLL_DELAY,
+                        });
+                    } else {
+                        quiet_ticks = quiet_ticks.saturating_add(1);
+                        tracing::trace!(game_id, quiet_ticks, "no new turn");
+                        let next_poll_delay = if quiet_ticks >= QUIET_TICKS_BEFORE_BACKOFF {
+                            MAX_POLL_DELAY
+                        } else {
+                            MIN_POLL_DELAY
+                        };
+                        let _ = reply.send(GameUpdate {
+                            new_turn: None,
+                            next_poll_delay,
+                        });
+                    }
+                }
+                GameCommand::Shutdown => break,
+            }
+        }
+    });
+
+    GameActorHandle { tx }
+}
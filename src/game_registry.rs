@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::game_actor::{self, GameActorHandle};
+use crate::storage::Storage;
+
+/// Creates per-game actors as games appear in the Star Realms activity feed
+/// and retires them when the caller reports a game finished, analogous to a
+/// `PlayerRegistry` or `RoomRegistry` in other actor-model servers. A game
+/// that leaves the feed without being retired keeps its actor.
+#[derive(Default)]
+pub struct GameRegistry {
+    actors: HashMap<i64, GameActorHandle>,
+}
+
+impl GameRegistry {
+    pub fn new() -> GameRegistry {
+        GameRegistry::default()
+    }
+
+    /// Get the actor for `game_id`, spawning one seeded from
+    /// `persisted_turns` if this is the first time we've seen it this
+    /// process.
+    pub fn handle_for(
+        &mut self,
+        game_id: i64,
+        storage: &Arc<Storage>,
+        persisted_turns: &HashMap<i64, String>,
+    ) -> GameActorHandle {
+        self.actors
+            .entry(game_id)
+            .or_insert_with(|| {
+                game_actor::spawn(
+                    game_id,
+                    persisted_turns.get(&game_id).cloned(),
+                    storage.clone(),
+                )
+            })
+            .clone()
+    }
+
+    /// Retire the actor for a game that's no longer active (e.g. finished),
+    /// stopping its task.
+    pub async fn retire(&mut self, game_id: i64) {
+        if let Some(handle) = self.actors.remove(&game_id) {
+            handle.shutdown().await;
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::TypeMapKey;
+use tokio::sync::RwLock;
+
+use crate::storage::Storage;
+
+pub struct GuildOptionsContainer;
+
+impl TypeMapKey for GuildOptionsContainer {
+    type Value = Arc<RwLock<GuildOptions>>;
+}
+
+/// Per-guild configuration, persisted to `storage`. Currently this is just
+/// the channel a guild wants Star Realms turn/challenge/finished
+/// notifications delivered to.
+pub struct GuildOptions {
+    storage: Arc<Storage>,
+    channels: HashMap<GuildId, ChannelId>,
+}
+
+impl GuildOptions {
+    /// Load every guild's registered notification channel from `storage`.
+    pub async fn load(storage: Arc<Storage>) -> Result<GuildOptions> {
+        let channels = storage
+            .load_guild_channels()
+            .await?
+            .into_iter()
+            .map(|(guild, channel)| (GuildId(guild as u64), ChannelId(channel as u64)))
+            .collect();
+
+        Ok(GuildOptions { storage, channels })
+    }
+
+    /// Register `channel` as the notification channel for `guild`.
+    pub async fn set_channel(&mut self, guild: GuildId, channel: ChannelId) -> Result<()> {
+        self.storage
+            .set_guild_channel(guild.0 as i64, channel.0 as i64)
+            .await?;
+        self.channels.insert(guild, channel);
+        Ok(())
+    }
+
+    /// The notification channels of every guild that has registered one.
+    pub fn channels(&self) -> impl Iterator<Item = &ChannelId> {
+        self.channels.values()
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use star_realms_rs::{Challenge, Game};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Mutex};
+
+/// A detected Star Realms activity event, as broadcast to subscribers.
+#[derive(Clone)]
+pub enum Event {
+    Turn(Arc<Game>),
+    Challenge(Arc<Challenge>),
+    Finished(Arc<Game>),
+}
+
+/// What a subscriber wants to hear about.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    /// Events involving a specific Star Realms player, by username.
+    Player(String),
+    /// Events for a specific game id.
+    Game(i64),
+}
+
+impl Filter {
+    fn matches(&self, event: &Event) -> bool {
+        match (self, event) {
+            (Filter::Game(id), Event::Turn(game)) | (Filter::Game(id), Event::Finished(game)) => {
+                *id == game.id
+            }
+            (Filter::Game(id), Event::Challenge(chal)) => *id == chal.id,
+            (Filter::Player(name), Event::Turn(game)) => game.which_turn() == *name,
+            (Filter::Player(name), Event::Finished(game)) => {
+                game.clientdata.p1_name == *name || game.clientdata.p2_name == *name
+            }
+            (Filter::Player(name), Event::Challenge(chal)) => {
+                chal.challengername == *name || chal.opponentname == *name
+            }
+        }
+    }
+}
+
+/// Fan-out hub for turn/challenge/finished events. Subscribers (a Discord
+/// user's DMs, or a channel) register an mpsc receiver filtered by player
+/// name or game id via `new_sub`; the poll loop `broadcast`s every detected
+/// event to whichever subscribers match.
+#[derive(Clone, Default)]
+pub struct Hub {
+    subscribers: Arc<Mutex<HashMap<u64, (Filter, mpsc::Sender<Event>)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Hub {
+    pub fn new() -> Hub {
+        Hub::default()
+    }
+
+    /// Register a new subscriber matching `filter`, returning an id to pass
+    /// to `unsubscribe` later and the receiving half of its channel.
+    pub async fn new_sub(&self, filter: Filter) -> (u64, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel(8);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribers.lock().await.insert(id, (filter, tx));
+        (id, rx)
+    }
+
+    /// Remove a subscriber registered by `new_sub`.
+    pub async fn unsubscribe(&self, id: u64) {
+        self.subscribers.lock().await.remove(&id);
+    }
+
+    /// Send `event` to every subscriber whose filter matches it, dropping
+    /// any whose receiver has gone away. A subscriber whose channel is
+    /// merely full (still alive, just slow to drain) is left in place.
+    pub async fn broadcast(&self, event: Event) {
+        let mut subs = self.subscribers.lock().await;
+        subs.retain(|_, (filter, tx)| {
+            if !filter.matches(&event) {
+                return true;
+            }
+            !matches!(tx.try_send(event.clone()), Err(TrySendError::Closed(_)))
+        });
+    }
+}
@@ -1,13 +1,15 @@
 use std::{
     collections::HashMap,
     env,
+    net::SocketAddr,
     sync::{atomic::AtomicBool, atomic::Ordering, Arc},
     time::Duration,
 };
 
 use serenity::{
     async_trait,
-    model::{channel::Message, gateway::Ready, id::ChannelId},
+    framework::standard::StandardFramework,
+    model::{gateway::Ready, id::ChannelId},
     prelude::*,
 };
 
@@ -15,16 +17,134 @@ use log::{self, info, error};
 use star_realms_rs::{Challenge, Game, StarRealms};
 
 use anyhow::Result;
-use tokio::time::Instant;
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+mod commands;
+mod game_actor;
+mod game_registry;
+mod guild_options;
+mod hub;
+mod metrics;
+mod storage;
+mod subscriptions;
+
+use commands::{GENERAL_GROUP, HELP};
+use game_registry::GameRegistry;
+use guild_options::{GuildOptions, GuildOptionsContainer};
+use hub::{Event, Hub};
+use storage::Storage;
+use subscriptions::{Subscriptions, SubscriptionsContainer};
+
+struct HubContainer;
+
+impl TypeMapKey for HubContainer {
+    type Value = Hub;
+}
+
+struct ShutdownContainer;
+
+impl TypeMapKey for ShutdownContainer {
+    type Value = Shutdown;
+}
+
+struct PollLoopHandleContainer;
+
+impl TypeMapKey for PollLoopHandleContainer {
+    /// Set once `Handler::ready` spawns the poll loop, so shutdown can await
+    /// it and know the in-flight tick has actually finished before the
+    /// shard manager (and the process) goes down.
+    type Value = Arc<AsyncMutex<Option<JoinHandle<()>>>>;
+}
+
+/// Coordinates graceful shutdown of the poll loop. `requested` lets the loop
+/// notice a shutdown without blocking, while `notify` wakes it out of its
+/// between-tick backoff immediately instead of waiting out the full 5s/60s.
+#[derive(Clone)]
+struct Shutdown {
+    requested: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    fn new() -> Shutdown {
+        Shutdown {
+            requested: Arc::new(AtomicBool::new(false)),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Ask the poll loop to stop after its current tick.
+    fn request(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+        self.notify.notify_waiters();
+    }
+
+    fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    /// Sleep for `duration` between ticks, waking early if shutdown is
+    /// requested so the bot doesn't sit out a full backoff on its way down.
+    ///
+    /// `Notify::notify_waiters` only wakes tasks already parked in
+    /// `notified()` — it doesn't store a permit for a call that arrives
+    /// later. So a `request()` that lands while a tick is still running
+    /// (not yet sleeping) would otherwise be missed entirely; check
+    /// `is_requested()` up front to cover that race.
+    async fn sleep_or_shutdown(&self, duration: Duration) {
+        if self.is_requested() {
+            return;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.notify.notified() => {}
+        }
+    }
+}
+
+/// Resolves once the process receives Ctrl+C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
 
 struct StarRealmsSharedContainer;
 
 struct StarRealmsShared {
     sr: StarRealms,
-    game_turns: HashMap<i64, Game>,
+    storage: Arc<Storage>,
+    /// Registry of per-game actors, each owning its own turn state so a
+    /// single poll tick doesn't need to lock the whole shared state while
+    /// every game is checked.
+    registry: GameRegistry,
+    /// Last-seen turn per game id, as persisted in `storage`. Used to seed
+    /// a game actor the first time we see its game this process (e.g. right
+    /// after a restart).
+    persisted_turns: HashMap<i64, String>,
     challenges: Vec<i64>,
     finished: Vec<i64>,
-    last_update: Instant,
 }
 
 impl TypeMapKey for StarRealmsSharedContainer {
@@ -32,8 +152,13 @@ impl TypeMapKey for StarRealmsSharedContainer {
 }
 
 impl StarRealmsShared {
-    ///Initialise the Star Realms client, and get the latest data.
-    async fn new() -> Result<StarRealmsShared> {
+    ///Initialise the Star Realms client, restore any previously persisted
+    ///state from `storage`, and get the latest data.
+    async fn new(storage: Arc<Storage>) -> Result<StarRealmsShared> {
+        let known_turns = storage.load_game_turns().await?;
+        let known_challenges = storage.load_challenges().await?;
+        let known_finished = storage.load_finished().await?;
+
         let mut initial = StarRealmsShared {
             sr: StarRealms::new(
                 env::var("SR_USERNAME")
@@ -44,138 +169,210 @@ impl StarRealmsShared {
                     .as_str(),
             )
             .await?,
-            game_turns: HashMap::new(),
-            challenges: vec![],
-            finished: vec![],
-            last_update: Instant::now(), //TODO: Maybe set to 0?
+            storage,
+            registry: GameRegistry::new(),
+            persisted_turns: known_turns,
+            challenges: known_challenges.into_iter().collect(),
+            finished: known_finished.into_iter().collect(),
         };
 
         info!("Caching SR client");
-        initial.check_turns().await;
-        initial.check_challenges().await;
-        initial.check_finished().await;
+        initial.poll().await;
         info!("Finished Caching SR client");
 
         Ok(initial)
     }
 
-    /// Check if any turns have updated since the last check
-    /// This returns a HashMap of GameID and the username of the player whose turn it is
-    async fn check_turns(&mut self) -> HashMap<i64, Game> {
-        //TODO: Maybe return Game instead?
-        let mut turns = HashMap::new();
-        let activity = self.sr.activity().await.expect("Could not get activity");
+    /// Fetch the latest activity from the Star Realms API once, and route
+    /// every active game to its per-game actor rather than locking shared
+    /// turn state for the whole batch. Returns whatever is new since the
+    /// last poll (turns, challenges, finished games) plus the shortest delay
+    /// any active game's actor recommended before polling again. Returns
+    /// `None` if the API call itself failed, so a transient error just
+    /// skips this tick instead of killing the poll loop.
+    #[tracing::instrument(skip(self))]
+    async fn poll(&mut self) -> Option<(Vec<Game>, Vec<Challenge>, Vec<Game>, Duration)> {
+        let timer = metrics::ACTIVITY_LATENCY.start_timer();
+        let activity = match self.sr.activity().await {
+            Ok(activity) => activity,
+            Err(why) => {
+                error!("Error fetching Star Realms activity: {:?}", why);
+                return None;
+            }
+        };
+        timer.observe_duration();
+
+        metrics::ACTIVE_GAMES.set(activity.activegames.len() as i64);
 
+        // The shortest delay any active game recommends wins, so a single
+        // game still flipping turns keeps the whole tick responsive even if
+        // every other tracked game has gone quiet.
+        let mut next_poll_delay: Option<Duration> = None;
+        let mut turns = vec![];
         for game in activity.activegames {
-            let turn = self.game_turns.get(&game.id);
-
-            let which_turn = game.which_turn();
-
-            if turn.is_none() {
-                info!("Found new game: {:?}", game);
-                turns.insert(game.id, game);
-            } else {
-                let turn = turn.unwrap();
-                if turn.which_turn() != which_turn {
-                    info!("Found new turn: {:?}", game);
-                    turns.insert(game.id, game);
-                } else {
-                    info!("Game {} already on last found turn", game.id);
-                }
+            let handle = self
+                .registry
+                .handle_for(game.id, &self.storage, &self.persisted_turns);
+            let update = handle.update(game).await;
+            next_poll_delay = Some(match next_poll_delay {
+                Some(delay) => delay.min(update.next_poll_delay),
+                None => update.next_poll_delay,
+            });
+            if let Some(game) = update.new_turn {
+                info!("Found new turn: {:?}", game);
+                turns.push(game);
             }
         }
-
+        let next_poll_delay = next_poll_delay.unwrap_or(game_actor::MIN_POLL_DELAY);
         if !turns.is_empty() {
-            self.game_turns.extend(turns.clone());
-            self.last_update = Instant::now();
+            metrics::TURNS_DETECTED.inc_by(turns.len() as u64);
         }
 
-        turns
-    }
-
-    async fn check_challenges(&mut self) -> Vec<Challenge> {
         let mut challenges = vec![];
-        let activity = self.sr.activity().await.expect("Could not get activity");
-
         for chal in activity.challenges {
             if !self.challenges.contains(&chal.id) {
                 self.challenges.push(chal.id);
+                if let Err(why) = self.storage.add_challenge(chal.id).await {
+                    error!("Error persisting challenge {}: {:?}", chal.id, why);
+                }
                 info!("Found new challenge: {:?}", chal);
                 challenges.push(chal);
             }
         }
-
         if !challenges.is_empty() {
-            self.last_update = Instant::now();
+            metrics::CHALLENGES_DETECTED.inc_by(challenges.len() as u64);
         }
-        challenges
-    }
 
-    async fn check_finished(&mut self) -> Vec<Game> {
         let mut finished = vec![];
-        let activity = self.sr.activity().await.expect("Could not get activity");
-
         for game in activity.finishedgames {
             if !self.finished.contains(&game.id) {
                 self.finished.push(game.id);
-                info!("Found new challenge: {:?}", game);
+                if let Err(why) = self.storage.add_finished(game.id).await {
+                    error!("Error persisting finished game {}: {:?}", game.id, why);
+                }
+                info!("Found new finished game: {:?}", game);
+                self.registry.retire(game.id).await;
                 finished.push(game);
             }
         }
-
         if !finished.is_empty() {
-            self.last_update = Instant::now();
+            metrics::FINISHED_DETECTED.inc_by(finished.len() as u64);
         }
-        finished
-    }
 
+        Some((turns, challenges, finished, next_poll_delay))
+    }
 }
 
-struct Handler {
-    looping: AtomicBool,
-}
-
-#[async_trait]
-impl EventHandler for Handler {
+/// Run one poll iteration: fetch the latest Star Realms activity, diff it
+/// against what's been seen before, and announce anything new to every
+/// guild's registered notification channel. Every detected turn, challenge,
+/// and finished game is persisted to storage as it's found, so there's
+/// nothing left to flush once the tick returns.
+async fn poll_tick(ctx: &Arc<Context>, ctx1: &Arc<Context>, shutdown: &Shutdown) {
+    let sr_lock = {
+        let data_read = ctx.data.read().await;
+
+        data_read
+            .get::<StarRealmsSharedContainer>()
+            .expect("Expected StarRealmsSharedContainer in TypeMap.")
+            .clone()
+    };
+
+    let guild_options_lock = {
+        let data_read = ctx.data.read().await;
+
+        data_read
+            .get::<GuildOptionsContainer>()
+            .expect("Expected GuildOptionsContainer in TypeMap.")
+            .clone()
+    };
+    let guild_options = guild_options_lock.read().await;
+    let channels: Vec<ChannelId> = guild_options.channels().copied().collect();
+
+    let hub = {
+        let data_read = ctx.data.read().await;
+
+        data_read
+            .get::<HubContainer>()
+            .expect("Expected HubContainer in TypeMap.")
+            .clone()
+    };
+
+    // Hold the write lock only for the poll itself; the channel sends and
+    // hub broadcast below are network I/O that shouldn't block other
+    // lockers (e.g. `!chal`) while they run.
+    let (turns, challenges, finished, next_poll_delay) = {
+        let mut sr = sr_lock.write().await;
+        match sr.poll().await {
+            Some((turns, challenges, finished, next_poll_delay)) => {
+                (turns, challenges, finished, next_poll_delay)
+            }
+            // The activity fetch failed and was already logged in `poll`;
+            // skip this tick and retry after the default short backoff.
+            None => (vec![], vec![], vec![], game_actor::MIN_POLL_DELAY),
+        }
+    };
+
+    for turn in turns {
+        for channel in &channels {
+            if let Err(why) = channel
+                .say(
+                    &ctx1.http,
+                    format!("Player's Turn: {} ({}) in game {} vs {} ({})", turn.which_turn(), turn.clientdata.get_auth(&turn.which_turn()).unwrap(), turn.id, &turn.opponentname, turn.clientdata.get_auth(&turn.opponentname).unwrap()),
+                )
+                .await
+            {
+                error!("Error sending message: {:?}", why);
+            }
+        }
+        hub.broadcast(Event::Turn(Arc::new(turn))).await;
+    }
 
-    async fn message(&self, ctx: Context, msg: Message) {
-        if msg.content.starts_with("!chal") {
-            let sr_lock = {
-                let data_read = ctx.data.read().await;
-                data_read
-                    .get::<StarRealmsSharedContainer>()
-                    .expect("Expected StarRealmsSharedContainer in TypeMap.")
-                    .clone()
-            };
-            let sr = sr_lock.write().await;
-            let activity = sr.sr.activity().await.expect("Could not get activity");
-            for chal in activity.challenges {
-                if let Err(why) = msg
-                    .channel_id
-                    .say(
-                        &ctx.http,
-                        format!(
-                            "Challenge from: {} to {}",
-                            chal.challengername, chal.opponentname
-                        ),
-                    )
-                    .await
-                {
-                    error!("Error sending message: {:?}", why);
-                }
+    for chal in challenges {
+        for channel in &channels {
+            if let Err(why) = channel
+                .say(
+                    &ctx1.http,
+                    format!(
+                        "{} is challenging {} to a game of Star Realms! ðŸš€ðŸš€ðŸš€",
+                        chal.challengername, chal.opponentname
+                    ),
+                )
+                .await
+            {
+                error!("Error sending message: {:?}", why);
             }
         }
-        if msg.content.to_lowercase().starts_with("!version") {
-            if let Err(why) = msg
-                .channel_id
-                .say(&ctx.http, format!("Starbot {}", env!("CARGO_PKG_VERSION")))
+        hub.broadcast(Event::Challenge(Arc::new(chal))).await;
+    }
+
+    for fin in finished {
+        for channel in &channels {
+            if let Err(why) = channel
+                .say(
+                    &ctx1.http,
+                    format!(
+                        "Game {} just finished, with {} at {} and {} at {}",
+                        fin.id, fin.clientdata.p1_name, fin.clientdata.p1_auth, fin.clientdata.p2_name, fin.clientdata.p2_auth
+                    ),
+                )
                 .await
             {
                 error!("Error sending message: {:?}", why);
             }
         }
+        hub.broadcast(Event::Finished(Arc::new(fin))).await;
     }
 
+    shutdown.sleep_or_shutdown(next_poll_delay).await;
+}
+
+struct Handler {
+    looping: AtomicBool,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
 
@@ -183,68 +380,32 @@ impl EventHandler for Handler {
         if !self.looping.load(Ordering::Relaxed) {
             let ctx1 = Arc::clone(&ctx);
 
-            tokio::spawn(async move {
-                loop {
-                    let sr_lock = {
-                        let data_read = ctx.data.read().await;
-
-                        data_read
-                            .get::<StarRealmsSharedContainer>()
-                            .expect("Expected StarRealmsSharedContainer in TypeMap.")
-                            .clone()
-                    };
-
-                    let mut sr = sr_lock.write().await;
-
-                    for turn in sr.check_turns().await {
-                        if let Err(why) = ChannelId(473189734873825293)
-                            .say(
-                                &ctx1.http,
-                                format!("Player's Turn: {} ({}) in game {} vs {} ({})", turn.1.which_turn(), turn.1.clientdata.get_auth(&turn.1.which_turn()).unwrap(), turn.0, &turn.1.opponentname, turn.1.clientdata.get_auth(&turn.1.opponentname).unwrap()),
-                            )
-                            .await
-                        {
-                            println!("Error sending message: {:?}", why);
-                        }
-                    }
-
-                    for chal in sr.check_challenges().await {
-                        if let Err(why) = ChannelId(473189734873825293)
-                            .say(
-                                &ctx1.http,
-                                format!(
-                                    "{} is challenging {} to a game of Star Realms! ðŸš€ðŸš€ðŸš€",
-                                    chal.challengername, chal.opponentname
-                                ),
-                            )
-                            .await
-                        {
-                            println!("Error sending message: {:?}", why);
-                        }
-                    }
-
-                    for fin in sr.check_finished().await {
-                        if let Err(why) = ChannelId(473189734873825293)
-                            .say(
-                                &ctx1.http,
-                                format!(
-                                    "Game {} just finished, with {} at {} and {} at {}",
-                                    fin.id, fin.clientdata.p1_name, fin.clientdata.p1_auth, fin.clientdata.p2_name, fin.clientdata.p2_auth
-                                ),
-                            )
-                            .await
-                        {
-                            println!("Error sending message: {:?}", why);
-                        }
-                    }
+            let (shutdown, poll_handle) = {
+                let data_read = ctx.data.read().await;
+                (
+                    data_read
+                        .get::<ShutdownContainer>()
+                        .expect("Expected ShutdownContainer in TypeMap.")
+                        .clone(),
+                    data_read
+                        .get::<PollLoopHandleContainer>()
+                        .expect("Expected PollLoopHandleContainer in TypeMap.")
+                        .clone(),
+                )
+            };
 
-                    if sr.last_update.elapsed().as_secs() >= (30 * 60) {
-                        tokio::time::sleep(Duration::from_secs(60)).await;
-                    } else {
-                        tokio::time::sleep(Duration::from_secs(5)).await;
+            let handle = tokio::spawn(async move {
+                loop {
+                    poll_tick(&ctx, &ctx1, &shutdown)
+                        .instrument(tracing::info_span!("poll_tick"))
+                        .await;
+                    if shutdown.is_requested() {
+                        info!("Poll loop stopped for shutdown");
+                        break;
                     }
                 }
             });
+            *poll_handle.lock().await = Some(handle);
         }
         self.looping.swap(true, Ordering::Relaxed);
     }
@@ -253,24 +414,66 @@ impl EventHandler for Handler {
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
-    env_logger::init();
+    tracing_log::LogTracer::init().expect("Failed to set log compatibility layer");
+    tracing_subscriber::fmt::init();
 
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let db_path = env::var("DATABASE_PATH").unwrap_or_else(|_| "starbot.sqlite".to_string());
+    let metrics_addr: SocketAddr = env::var("METRICS_ADDR")
+        .unwrap_or_else(|_| "0.0.0.0:9898".to_string())
+        .parse()
+        .expect("Expected METRICS_ADDR to be a valid socket address");
+
+    tokio::spawn(metrics::serve(metrics_addr));
+
+    let framework = StandardFramework::new()
+        .configure(|c| c.prefix("!").case_insensitive(true))
+        .group(&GENERAL_GROUP)
+        .help(&HELP);
 
     let mut client = Client::builder(&token)
         .event_handler(Handler {
             looping: AtomicBool::new(false),
         })
+        .framework(framework)
         .await
         .expect("Err creating client");
 
+    let shutdown = Shutdown::new();
+    let poll_handle: Arc<AsyncMutex<Option<JoinHandle<()>>>> = Arc::new(AsyncMutex::new(None));
+
     {
+        let storage = Arc::new(Storage::connect(&db_path).await?);
+        let guild_options = GuildOptions::load(storage.clone()).await?;
+        let hub = Hub::new();
+        let subscriptions =
+            Subscriptions::load(storage.clone(), hub.clone(), client.cache_and_http.http.clone())
+                .await?;
+
         let mut data = client.data.write().await;
         data.insert::<StarRealmsSharedContainer>(Arc::new(RwLock::new(
-            StarRealmsShared::new().await?,
-        )))
+            StarRealmsShared::new(storage).await?,
+        )));
+        data.insert::<GuildOptionsContainer>(Arc::new(RwLock::new(guild_options)));
+        data.insert::<HubContainer>(hub);
+        data.insert::<SubscriptionsContainer>(Arc::new(RwLock::new(subscriptions)));
+        data.insert::<ShutdownContainer>(shutdown.clone());
+        data.insert::<PollLoopHandleContainer>(poll_handle.clone());
     }
 
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutdown signal received, stopping poll loop and client");
+        shutdown.request();
+        if let Some(handle) = poll_handle.lock().await.take() {
+            if let Err(why) = handle.await {
+                error!("Poll loop task panicked during shutdown: {:?}", why);
+            }
+        }
+        shard_manager.lock().await.shutdown_all().await;
+    });
+
     if let Err(why) = client.start().await {
         println!("Client error: {:?}", why);
     }
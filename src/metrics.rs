@@ -0,0 +1,89 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry, TextEncoder};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Number of Star Realms games currently tracked.
+pub static ACTIVE_GAMES: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::with_opts(Opts::new(
+        "starbot_active_games",
+        "Number of Star Realms games currently tracked",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Total new turns detected since boot.
+pub static TURNS_DETECTED: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::with_opts(Opts::new(
+        "starbot_turns_detected_total",
+        "Total new turns detected since boot",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Total new challenges detected since boot.
+pub static CHALLENGES_DETECTED: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::with_opts(Opts::new(
+        "starbot_challenges_detected_total",
+        "Total new challenges detected since boot",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Total newly finished games detected since boot.
+pub static FINISHED_DETECTED: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::with_opts(Opts::new(
+        "starbot_finished_detected_total",
+        "Total newly finished games detected since boot",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Latency of `StarRealms::activity()` calls, in seconds.
+pub static ACTIVITY_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    let histogram = Histogram::with_opts(HistogramOpts::new(
+        "starbot_activity_latency_seconds",
+        "Latency of StarRealms activity() calls",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+async fn serve_metrics(_req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let encoder = TextEncoder::new();
+    let metric_families = REGISTRY.gather();
+
+    let mut buffer = vec![];
+    if let Err(why) = encoder.encode(&metric_families, &mut buffer) {
+        error!("Error encoding metrics: {:?}", why);
+    }
+
+    Ok(Response::new(Body::from(buffer)))
+}
+
+/// Serve Prometheus metrics over HTTP at `GET /metrics` on `addr` until the
+/// process exits.
+pub async fn serve(addr: SocketAddr) {
+    let make_svc =
+        make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(serve_metrics)) });
+
+    info!("Serving metrics on http://{}/metrics", addr);
+    if let Err(why) = Server::bind(&addr).serve(make_svc).await {
+        error!("Metrics server error: {:?}", why);
+    }
+}
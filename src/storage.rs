@@ -0,0 +1,190 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+/// Persists the state that used to live only in `StarRealmsShared`'s in-memory
+/// fields, so a bot restart doesn't re-announce every active turn, challenge,
+/// and finished game as "new".
+pub struct Storage {
+    pool: SqlitePool,
+}
+
+impl Storage {
+    /// Open (and create if needed) the SQLite database at `path`, running
+    /// migrations to bring the schema up to date.
+    pub async fn connect(path: &str) -> Result<Storage> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS game_turns (
+                game_id INTEGER PRIMARY KEY,
+                turn TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS challenges (
+                challenge_id INTEGER PRIMARY KEY
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS finished_games (
+                game_id INTEGER PRIMARY KEY
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS guild_channels (
+                guild_id INTEGER PRIMARY KEY,
+                channel_id INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS subscriptions (
+                user_id INTEGER PRIMARY KEY,
+                player TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(Storage { pool })
+    }
+
+    /// Load the last-seen turn for every tracked game, keyed by game id.
+    pub async fn load_game_turns(&self) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as("SELECT game_id, turn FROM game_turns")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Record the last-seen turn for `game_id`.
+    pub async fn set_game_turn(&self, game_id: i64, turn: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO game_turns (game_id, turn) VALUES (?, ?)
+             ON CONFLICT(game_id) DO UPDATE SET turn = excluded.turn",
+        )
+        .bind(game_id)
+        .bind(turn)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load every challenge id we've already announced.
+    pub async fn load_challenges(&self) -> Result<HashSet<i64>> {
+        let rows: Vec<(i64,)> = sqlx::query_as("SELECT challenge_id FROM challenges")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Mark `challenge_id` as announced.
+    pub async fn add_challenge(&self, challenge_id: i64) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO challenges (challenge_id) VALUES (?)")
+            .bind(challenge_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load every finished-game id we've already announced.
+    pub async fn load_finished(&self) -> Result<HashSet<i64>> {
+        let rows: Vec<(i64,)> = sqlx::query_as("SELECT game_id FROM finished_games")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Mark `game_id` as an announced finished game.
+    pub async fn add_finished(&self, game_id: i64) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO finished_games (game_id) VALUES (?)")
+            .bind(game_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load the notification channel registered for every guild.
+    pub async fn load_guild_channels(&self) -> Result<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT guild_id, channel_id FROM guild_channels")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Register `channel_id` as the notification channel for `guild_id`.
+    pub async fn set_guild_channel(&self, guild_id: i64, channel_id: i64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO guild_channels (guild_id, channel_id) VALUES (?, ?)
+             ON CONFLICT(guild_id) DO UPDATE SET channel_id = excluded.channel_id",
+        )
+        .bind(guild_id)
+        .bind(channel_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load the Star Realms player name each subscribed Discord user wants
+    /// notifications for, keyed by Discord user id.
+    pub async fn load_subscriptions(&self) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as("SELECT user_id, player FROM subscriptions")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Subscribe `user_id` to notifications for `player`.
+    pub async fn set_subscription(&self, user_id: i64, player: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO subscriptions (user_id, player) VALUES (?, ?)
+             ON CONFLICT(user_id) DO UPDATE SET player = excluded.player",
+        )
+        .bind(user_id)
+        .bind(player)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove `user_id`'s subscription, if any.
+    pub async fn remove_subscription(&self, user_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM subscriptions WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
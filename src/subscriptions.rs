@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::error;
+use serenity::http::Http;
+use serenity::model::id::UserId;
+use serenity::prelude::TypeMapKey;
+use tokio::sync::{mpsc, RwLock};
+
+use crate::hub::{Event, Filter, Hub};
+use crate::storage::Storage;
+
+pub struct SubscriptionsContainer;
+
+impl TypeMapKey for SubscriptionsContainer {
+    type Value = Arc<RwLock<Subscriptions>>;
+}
+
+/// Command sent to a per-user subscription actor.
+enum SubscriberCommand {
+    Shutdown,
+}
+
+/// A handle to a running per-user subscription task. Cheap to clone.
+#[derive(Clone)]
+struct SubscriberHandle {
+    tx: mpsc::Sender<SubscriberCommand>,
+}
+
+impl SubscriberHandle {
+    async fn shutdown(&self) {
+        let _ = self.tx.send(SubscriberCommand::Shutdown).await;
+    }
+}
+
+/// Spawn a task that subscribes to `hub` for `filter` and DMs `user`
+/// whenever a matching event comes in, until told to shut down.
+fn spawn(user: UserId, filter: Filter, hub: Hub, http: Arc<Http>) -> SubscriberHandle {
+    let (tx, mut commands) = mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let (sub_id, mut events) = hub.new_sub(filter).await;
+
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    match event {
+                        Some(event) => {
+                            if let Err(why) = dm(&http, user, event).await {
+                                error!("Error DMing subscriber {}: {:?}", user, why);
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                command = commands.recv() => {
+                    match command {
+                        Some(SubscriberCommand::Shutdown) | None => break,
+                    }
+                }
+            }
+        }
+
+        hub.unsubscribe(sub_id).await;
+    });
+
+    SubscriberHandle { tx }
+}
+
+async fn dm(http: &Arc<Http>, user: UserId, event: Event) -> Result<()> {
+    let message = match event {
+        Event::Turn(game) => format!(
+            "It's your turn in game {} vs {}",
+            game.id, game.opponentname
+        ),
+        Event::Challenge(chal) => format!(
+            "{} is challenging {} to a game of Star Realms!",
+            chal.challengername, chal.opponentname
+        ),
+        Event::Finished(game) => format!("Your game {} has just finished", game.id),
+    };
+
+    user.create_dm_channel(http).await?.say(http, message).await?;
+    Ok(())
+}
+
+/// Maps Discord users to the Star Realms player name they want turn and
+/// challenge notifications for, persisted to `storage`, and owns the
+/// per-user DM tasks subscribed to `hub`.
+pub struct Subscriptions {
+    storage: Arc<Storage>,
+    hub: Hub,
+    http: Arc<Http>,
+    actors: HashMap<UserId, SubscriberHandle>,
+}
+
+impl Subscriptions {
+    /// Restore every persisted subscription from `storage` and spawn its DM
+    /// task against `hub`.
+    pub async fn load(storage: Arc<Storage>, hub: Hub, http: Arc<Http>) -> Result<Subscriptions> {
+        let known = storage.load_subscriptions().await?;
+
+        let mut actors = HashMap::new();
+        for (user_id, player) in known {
+            let user = UserId(user_id as u64);
+            actors.insert(
+                user,
+                spawn(user, Filter::Player(player), hub.clone(), http.clone()),
+            );
+        }
+
+        Ok(Subscriptions {
+            storage,
+            hub,
+            http,
+            actors,
+        })
+    }
+
+    /// Subscribe `user` to turn/challenge notifications for `player`,
+    /// replacing any previous subscription they had.
+    pub async fn subscribe(&mut self, user: UserId, player: String) -> Result<()> {
+        self.storage.set_subscription(user.0 as i64, &player).await?;
+
+        if let Some(old) = self.actors.remove(&user) {
+            old.shutdown().await;
+        }
+        self.actors.insert(
+            user,
+            spawn(user, Filter::Player(player), self.hub.clone(), self.http.clone()),
+        );
+
+        Ok(())
+    }
+
+    /// Remove `user`'s subscription, if any.
+    pub async fn unsubscribe(&mut self, user: UserId) -> Result<()> {
+        self.storage.remove_subscription(user.0 as i64).await?;
+
+        if let Some(handle) = self.actors.remove(&user) {
+            handle.shutdown().await;
+        }
+
+        Ok(())
+    }
+}